@@ -22,7 +22,7 @@ fn main() {
     let config = match aws_cred.read_config(file_path) {
         Ok(config) => config,
         Err(e) => {
-            println!("Error {:?}", e);
+            println!("Error {e}");
             std::process::exit(1);
         }
     };
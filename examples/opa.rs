@@ -0,0 +1,173 @@
+use clotho::{AWSCredential, Config, Decision, DenyReason, NoopObserver, PolicyHook};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, ValueEnum};
+use hudsucker::hyper::{body, client::HttpConnector, Body, Client, Method, Request};
+use tracing::warn;
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+/// Evaluate `Authorization` header values, one per line on stdin, against a config and an Open
+/// Policy Agent server. OPA's answer overrides the config
+#[derive(Parser, Debug)]
+#[command(version, about="A clotho Open Policy Agent example", long_about = None)]
+struct CliArgs {
+    /// Config file location
+    #[clap(short, long)]
+    config: PathBuf,
+
+    /// OPA data API document returning a boolean, e.g. `http://localhost:8181/v1/data/clotho/allow`
+    #[clap(long)]
+    opa_url: String,
+
+    /// Seconds to cache OPA's answer for a key, region and service
+    #[clap(long, default_value_t = 30)]
+    cache_ttl: u64,
+
+    /// What to do when OPA is unreachable or doesn't answer a boolean
+    #[clap(long, value_enum, default_value_t = OnOpaError::Closed)]
+    on_opa_error: OnOpaError,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OnOpaError {
+    /// Fail open, keep the config's decision
+    Open,
+    /// Fail closed, deny the request
+    Closed,
+}
+
+type CacheKey = (String, String, String);
+
+/// A `PolicyHook` querying OPA's data API with the credential as `input`
+struct OpaPolicyHook {
+    client: Client<HttpConnector>,
+    url: String,
+    ttl: Duration,
+    on_error: OnOpaError,
+    cache: Mutex<HashMap<CacheKey, (Instant, Option<Decision>)>>,
+}
+
+impl OpaPolicyHook {
+    /// Ask OPA, `None` when the document is undefined
+    async fn query(&self, credential: &AWSCredential) -> Result<Option<bool>, String> {
+        let input = serde_json::json!({
+            "input": {
+                "account_id": credential.account_id,
+                "key_type": credential.key_type().to_string(),
+                "region": credential.region,
+                "service": credential.service,
+                "date": credential.date.to_string(),
+            }
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("content-type", "application/json")
+            .body(Body::from(input.to_string()))
+            .map_err(|e| e.to_string())?;
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("OPA answered {}", response.status()));
+        }
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+        let answer: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+        match answer.get("result") {
+            None => Ok(None),
+            Some(serde_json::Value::Bool(allowed)) => Ok(Some(*allowed)),
+            Some(other) => Err(format!("Expected a boolean result, got {other}")),
+        }
+    }
+}
+
+#[hudsucker::async_trait::async_trait]
+impl PolicyHook for OpaPolicyHook {
+    async fn evaluate(&self, credential: &AWSCredential) -> Option<Decision> {
+        let key = (
+            credential.access_key_id.clone(),
+            credential.region.clone(),
+            credential.service.clone(),
+        );
+        if let Some((at, decision)) = self.cache.lock().unwrap().get(&key) {
+            if at.elapsed() < self.ttl {
+                return decision.clone();
+            }
+        }
+
+        let decision = match self.query(credential).await {
+            Ok(answer) => answer.map(|allowed| {
+                if allowed {
+                    Decision::allowed()
+                } else {
+                    Decision::denied(DenyReason::DeniedByPolicy)
+                }
+            }),
+            // Errors aren't cached, the next request asks OPA again
+            Err(e) => {
+                warn!(error = e, "OPA query failed");
+                return match self.on_error {
+                    OnOpaError::Open => None,
+                    OnOpaError::Closed => Some(Decision::denied(DenyReason::DeniedByPolicy)),
+                };
+            }
+        };
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), decision.clone()));
+        decision
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = CliArgs::parse();
+
+    let subscriber = FmtSubscriber::builder()
+        .with_env_filter(EnvFilter::from_default_env())
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("failed setting tracing");
+
+    let config = match Config::from_file(args.config) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Error {e}");
+            std::process::exit(1);
+        }
+    };
+    let hook = OpaPolicyHook {
+        client: Client::new(),
+        url: args.opa_url,
+        ttl: Duration::from_secs(args.cache_ttl),
+        on_error: args.on_opa_error,
+        cache: Mutex::new(HashMap::new()),
+    };
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let aws_cred = match AWSCredential::new_from_http_authz(&line) {
+            Ok(aws_cred) => aws_cred,
+            Err(e) => {
+                println!("{e}");
+                continue;
+            }
+        };
+        let decision = aws_cred
+            .authorize_hooked(&config, &hook, &NoopObserver)
+            .await;
+        match decision.reason {
+            None => println!("{}: allowed", aws_cred.account_id),
+            Some(reason) => println!("{}: denied, {reason}", aws_cred.account_id),
+        }
+    }
+}
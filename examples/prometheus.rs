@@ -0,0 +1,72 @@
+use clotho::{AWSCredential, Config};
+use std::io::BufRead;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::Parser;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+/// Evaluate `Authorization` header values, one per line on stdin, against a config while serving
+/// the library's metrics for Prometheus to scrape
+#[derive(Parser, Debug)]
+#[command(version, about="A clotho Prometheus metrics example", long_about = None)]
+struct CliArgs {
+    /// Config file location
+    #[clap(short, long)]
+    config: PathBuf,
+
+    /// Address the `/metrics` endpoint listens on
+    #[clap(long, default_value = "127.0.0.1:9000")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = CliArgs::parse();
+
+    let subscriber = FmtSubscriber::builder()
+        .with_env_filter(EnvFilter::from_default_env())
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("failed setting tracing");
+
+    // Every `counter!` and `histogram!` of the library now lands in this exporter
+    if let Err(e) = PrometheusBuilder::new()
+        .with_http_listener(args.listen)
+        .install()
+    {
+        println!("Error {e}");
+        std::process::exit(1);
+    }
+
+    let config = match Config::from_file(args.config) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Error {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // Reading stdin blocks, keep it off the runtime serving the metrics
+    tokio::task::spawn_blocking(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let aws_cred = match AWSCredential::new_from_http_authz(&line) {
+                Ok(aws_cred) => aws_cred,
+                Err(e) => {
+                    println!("{e}");
+                    continue;
+                }
+            };
+            match aws_cred.authorize(&config).reason {
+                None => println!("{}: allowed", aws_cred.account_id),
+                Some(reason) => println!("{}: denied, {reason}", aws_cred.account_id),
+            }
+        }
+    })
+    .await
+    .expect("reading stdin failed");
+
+    println!("stdin closed, metrics are still served until interrupted");
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use clotho::{AWSCredential, AccountDecoder, OfflineDecoder};
+use libfuzzer_sys::fuzz_target;
+
+// The decoding arithmetic on arbitrary bytes, and the offline decoder `AWSCredential::new` uses
+// with its length and Base32 checks in front of it
+fuzz_target!(|data: &[u8]| {
+    let _ = AWSCredential::decode_account_id(data);
+    if let Ok(access_key_id) = std::str::from_utf8(data) {
+        let _ = OfflineDecoder.decode(access_key_id);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use clotho::AWSCredential;
+use libfuzzer_sys::fuzz_target;
+
+// Proxies hand `new_from_http_authz` whatever the client sent, it has to return an error for
+// anything it can't parse, never panic
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = std::str::from_utf8(data) {
+        let _ = AWSCredential::new_from_http_authz(header);
+    }
+});
@@ -0,0 +1,169 @@
+//! Code shared by the `clothohud` and `squid-icap` proxies
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser};
+use clap::ValueEnum;
+use clotho::{AWSCredential, Config, ConfigStore, LogDecisions};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, EnvFilter, FmtSubscriber};
+
+/// Writes to `<path>.<YYYY-MM-DD>`, moving on to a new file when the UTC date changes and
+/// deleting the oldest files beyond `max_log_files` when set
+fn daily_log_file(path: &Path, max_log_files: Option<usize>) -> io::Result<RollingFileAppender> {
+    let Some(prefix) = path.file_name().and_then(|name| name.to_str()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} doesn't name a file", path.display()),
+        ));
+    };
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut builder = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(prefix);
+    if let Some(max_log_files) = max_log_files {
+        builder = builder.max_log_files(max_log_files);
+    }
+    builder.build(directory).map_err(io::Error::other)
+}
+
+/// Log to the daily rotated `log_file` when set, keeping at most `max_log_files` of them,
+/// otherwise to stdout
+pub fn init_tracing(log_file: Option<PathBuf>, max_log_files: Option<usize>) -> io::Result<()> {
+    let builder = FmtSubscriber::builder().with_env_filter(EnvFilter::new("debug"));
+    let subscriber = match log_file {
+        Some(path) => builder
+            .with_ansi(false)
+            .with_writer(BoxMakeWriter::new(daily_log_file(&path, max_log_files)?))
+            .finish(),
+        None => builder.with_writer(BoxMakeWriter::new(io::stdout)).finish(),
+    };
+    tracing::subscriber::set_global_default(subscriber).expect("failed setting tracing");
+    Ok(())
+}
+
+/// Fill in the credential fields of the current request span
+pub fn record_credential(aws_cred: &AWSCredential) {
+    let span = tracing::Span::current();
+    span.record("account_id", aws_cred.account_id.as_str());
+    span.record("region", aws_cred.region.as_str());
+    span.record("service", aws_cred.service.as_str());
+}
+
+/// Log every issue `Config::validate` finds, exiting when `strict`
+pub fn check_config(config: &Config, strict: bool) {
+    let Err(issues) = config.validate() else {
+        return;
+    };
+    for issue in &issues {
+        tracing::error!(error = %issue, "Invalid config");
+    }
+    if strict {
+        tracing::error!("Exiting, --strict-config is set");
+        std::process::exit(1);
+    }
+}
+
+/// Parses `--log-decisions`, listing the values in `--help`
+pub fn log_decisions_parser() -> impl TypedValueParser<Value = LogDecisions> {
+    PossibleValuesParser::new([
+        PossibleValue::new("all").help("Allowed and denied requests"),
+        PossibleValue::new("deny").help("Only denied requests"),
+        PossibleValue::new("allow").help("Only allowed requests, e.g. for an audit trail"),
+        PossibleValue::new("none").help("No decision is logged"),
+    ])
+    .try_map(|value| value.parse::<LogDecisions>())
+}
+
+/// Body format of the deny responses
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ErrorFormat {
+    /// A plain text message
+    Text,
+    /// `{"error":"forbidden","reason":"...","account_id":"..."}`, `bad_request` for malformed
+    /// credentials in `clothohud`
+    Json,
+}
+
+/// The JSON deny body, `error` named after the `status`, e.g. `forbidden`. `reason` is a stable
+/// identifier, e.g. `account_not_allowed`, the `account_id` is included when known
+pub fn json_error(status: http::StatusCode, reason: &str, account_id: Option<&str>) -> String {
+    let error = status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_ascii_lowercase()
+        .replace(' ', "_");
+    let mut body = serde_json::json!({"error": error, "reason": reason});
+    if let Some(account_id) = account_id {
+        body["account_id"] = account_id.into();
+    }
+    body.to_string()
+}
+
+/// Handling of requests when no config is available
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OnConfigError {
+    /// Fail closed, deny the request
+    Deny,
+    /// Fail open, allow the request
+    Allow,
+}
+
+/// Reload the config on every SIGHUP, the current config is kept when the reload fails
+#[cfg(unix)]
+pub async fn reload_on_sighup(config: Arc<ConfigStore>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to install the SIGHUP handler");
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        match config.reload_async().await {
+            Ok(_) => tracing::info!("Config reloaded on SIGHUP"),
+            Err(e) => tracing::error!(error = %e, "Config reload on SIGHUP failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_log_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clotho.log");
+        for day in 1..=3 {
+            std::fs::write(dir.path().join(format!("clotho.log.2024-05-0{day}")), "").unwrap();
+        }
+        std::fs::write(dir.path().join("other.log"), "").unwrap();
+
+        let mut log = daily_log_file(&path, Some(2)).unwrap();
+        io::Write::write_all(&mut log, b"first\n").unwrap();
+        let today = chrono::Utc::now().date_naive();
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        // The oldest files beyond the limit are deleted, files of other names are kept
+        assert_eq!(names.len(), 3, "{names:?}");
+        assert!(names.contains(&format!("clotho.log.{today}")));
+        assert!(names.contains(&"other.log".to_string()));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(format!("clotho.log.{today}"))).unwrap(),
+            "first\n"
+        );
+
+        assert!(daily_log_file(Path::new("/"), None).is_err());
+    }
+}